@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::ops::ControlFlow;
 
 #[derive(Clone, Debug)]
@@ -11,6 +13,20 @@ enum Op {
   Dup,
   Swap,
   Drop,
+  Jmp(u16),
+  Jz(u16),
+  Jnz(u16),
+  Call(u16),
+  Ret,
+  Div,
+  Mod,
+  And,
+  Or,
+  Xor,
+  Not,
+  Eq,
+  Lt,
+  Gt,
 }
 
 impl Op {
@@ -25,20 +41,41 @@ impl Op {
       Op::Dup => 0x07,
       Op::Swap => 0x08,
       Op::Drop => 0x09,
+      Op::Jmp(_) => 0x0A,
+      Op::Jz(_) => 0x0B,
+      Op::Jnz(_) => 0x0C,
+      Op::Call(_) => 0x0D,
+      Op::Ret => 0x0E,
+      Op::Div => 0x0F,
+      Op::Mod => 0x10,
+      Op::And => 0x11,
+      Op::Or => 0x12,
+      Op::Xor => 0x13,
+      Op::Not => 0x14,
+      Op::Eq => 0x15,
+      Op::Lt => 0x16,
+      Op::Gt => 0x17,
     }
   }
 
   fn to_bytecode(&self) -> Vec<u8> {
     match self {
       Op::Push(value) => vec![self.opcode(), *value],
+      Op::Jmp(target) | Op::Jz(target) | Op::Jnz(target) | Op::Call(target) => {
+        let [lo, hi]: [u8; 2] = target.to_le_bytes();
+        vec![self.opcode(), lo, hi]
+      },
       _ => vec![self.opcode()],
     }
   }
 
-  fn from_parts(parts: &[&str]) -> Self {
-    match parts {
+  fn from_parts(parts: &[&str], labels: &HashMap<String, usize>) -> Result<Self, AssembleError> {
+    let op: Op = match parts {
       ["PUSH", value] => {
-        let value: u8 = value.parse().expect("Expected an 8-bit number");
+        let value: u8 = value.parse().map_err(|_| AssembleError::InvalidOperand {
+          instruction: parts.join(" "),
+          reason: "expected an 8-bit number".to_string(),
+        })?;
         Op::Push(value)
       },
       ["ADD"] => Op::Add,
@@ -49,34 +86,260 @@ impl Op {
       ["DUP"] => Op::Dup,
       ["SWAP"] => Op::Swap,
       ["DROP"] => Op::Drop,
-      _ => panic!("Unknown or malformed instruction: {parts:?}"),
+      ["JMP", label] => Op::Jmp(resolve_label(labels, label)?),
+      ["JZ", label] => Op::Jz(resolve_label(labels, label)?),
+      ["JNZ", label] => Op::Jnz(resolve_label(labels, label)?),
+      ["CALL", label] => Op::Call(resolve_label(labels, label)?),
+      ["RET"] => Op::Ret,
+      ["DIV"] => Op::Div,
+      ["MOD"] => Op::Mod,
+      ["AND"] => Op::And,
+      ["OR"] => Op::Or,
+      ["XOR"] => Op::Xor,
+      ["NOT"] => Op::Not,
+      ["EQ"] => Op::Eq,
+      ["LT"] => Op::Lt,
+      ["GT"] => Op::Gt,
+      _ => {
+        return Err(AssembleError::MalformedInstruction(parts.join(" ")));
+      },
+    };
+
+    Ok(op)
+  }
+
+  /// Mnemonic for this op's opcode, with no operand rendered. Used to label
+  /// jump/call instructions in trace output once their target is resolved
+  /// against the symbol table.
+  fn mnemonic(&self) -> &'static str {
+    match self {
+      Op::Push(_) => "PUSH",
+      Op::Add => "ADD",
+      Op::Sub => "SUB",
+      Op::Mul => "MUL",
+      Op::Print => "PRINT",
+      Op::PrintTop => "PRINT_TOP",
+      Op::Dup => "DUP",
+      Op::Swap => "SWAP",
+      Op::Drop => "DROP",
+      Op::Jmp(_) => "JMP",
+      Op::Jz(_) => "JZ",
+      Op::Jnz(_) => "JNZ",
+      Op::Call(_) => "CALL",
+      Op::Ret => "RET",
+      Op::Div => "DIV",
+      Op::Mod => "MOD",
+      Op::And => "AND",
+      Op::Or => "OR",
+      Op::Xor => "XOR",
+      Op::Not => "NOT",
+      Op::Eq => "EQ",
+      Op::Lt => "LT",
+      Op::Gt => "GT",
     }
   }
+
+  fn jump_target(&self) -> Option<u16> {
+    match self {
+      Op::Jmp(target) | Op::Jz(target) | Op::Jnz(target) | Op::Call(target) => Some(*target),
+      _ => None,
+    }
+  }
+
+  /// Renders this op back into assembly source. Jump/call targets are
+  /// printed as synthetic `L<offset>` labels so that, paired with the
+  /// label definitions `disassemble` emits, the output reassembles.
+  fn to_source(&self) -> String {
+    match self {
+      Op::Push(value) => format!("PUSH {value}"),
+      Op::Jmp(target) | Op::Jz(target) | Op::Jnz(target) | Op::Call(target) => {
+        format!("{} L{target}", self.mnemonic())
+      },
+      _ => self.mnemonic().to_string(),
+    }
+  }
+}
+
+fn resolve_label(labels: &HashMap<String, usize>, name: &str) -> Result<u16, AssembleError> {
+  labels
+    .get(name)
+    .map(|&offset| offset as u16)
+    .ok_or_else(|| AssembleError::UndefinedLabel(name.to_string()))
 }
 
+/// Number of bytecode bytes a mnemonic will occupy once assembled, without
+/// needing the operand to already be resolved. Used by the label-collection
+/// pass to compute offsets before any bytes are emitted.
+fn instruction_size(mnemonic: &str) -> usize {
+  match mnemonic {
+    "PUSH" => 2,
+    "JMP" | "JZ" | "JNZ" | "CALL" => 3,
+    _ => 1,
+  }
+}
+
+/// Errors that can occur while turning source text into bytecode.
+#[derive(Debug)]
+enum AssembleError {
+  MalformedInstruction(String),
+  InvalidOperand { instruction: String, reason: String },
+  UndefinedLabel(String),
+}
+
+impl fmt::Display for AssembleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AssembleError::MalformedInstruction(instruction) => {
+        write!(f, "unknown or malformed instruction: {instruction}")
+      },
+      AssembleError::InvalidOperand { instruction, reason } => {
+        write!(f, "invalid operand in `{instruction}`: {reason}")
+      },
+      AssembleError::UndefinedLabel(label) => write!(f, "undefined label: {label}"),
+    }
+  }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// Errors that can occur while executing bytecode.
+#[derive(Debug)]
+enum VmError {
+  StackUnderflow { op: &'static str },
+  UnknownOpcode(u8),
+  UnexpectedEndOfProgram,
+  DivisionByZero,
+  JumpOutOfBounds(usize),
+  ExecutionLimitExceeded(u64),
+}
+
+impl fmt::Display for VmError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VmError::StackUnderflow { op } => write!(f, "stack underflow in {op}"),
+      VmError::UnknownOpcode(opcode) => write!(f, "unknown opcode: {opcode:#04x}"),
+      VmError::UnexpectedEndOfProgram => write!(f, "unexpected end of program"),
+      VmError::DivisionByZero => write!(f, "division by zero"),
+      VmError::JumpOutOfBounds(target) => write!(f, "jump target out of bounds: {target}"),
+      VmError::ExecutionLimitExceeded(steps) => {
+        write!(f, "execution limit exceeded after {steps} steps")
+      },
+    }
+  }
+}
+
+impl std::error::Error for VmError {}
+
 #[derive(Debug)]
 struct VM {
   stack: Vec<u8>,
+  return_stack: Vec<usize>,
   ip: usize,
   program: Vec<u8>,
+  clock: u64,
+  max_steps: Option<u64>,
+  trace: bool,
+  symbols: HashMap<u16, String>,
 }
 
 impl VM {
   fn new(program: Vec<u8>) -> Self {
     Self {
       stack: vec![],
+      return_stack: vec![],
       ip: 0,
       program,
+      clock: 0,
+      max_steps: None,
+      trace: false,
+      symbols: HashMap::new(),
     }
   }
 
-  fn run(&mut self) {
+  /// Labels the VM's symbol table so trace output can show `JMP loop`
+  /// instead of `JMP L12`.
+  fn set_symbols(&mut self, symbols: HashMap<u16, String>) {
+    self.symbols = symbols;
+  }
+
+  /// Builds a VM that aborts with `VmError::ExecutionLimitExceeded` instead
+  /// of executing more than `max_steps` instructions, so embedders can run
+  /// untrusted bytecode without risking an infinite loop.
+  fn with_limit(program: Vec<u8>, max_steps: u64) -> Self {
+    Self {
+      max_steps: Some(max_steps),
+      ..Self::new(program)
+    }
+  }
+
+  fn run(&mut self) -> Result<(), VmError> {
     while self.ip < self.program.len() {
-      self.run_instruction();
+      if self.trace {
+        self.trace_instruction();
+      }
+      self.run_instruction()?;
     }
+
+    Ok(())
   }
 
-  fn run_instruction(&mut self) {
+  /// Prints `ip`, the decoded instruction at `ip`, and the current stack
+  /// before it executes, so users can step through a run. Mirrors
+  /// `run_instruction`'s bounds/opcode checks rather than panicking, so
+  /// tracing never has a worse failure mode than non-traced execution:
+  /// `run_instruction` reports the real error on the very next call.
+  fn trace_instruction(&self) {
+    let ip: usize = self.ip;
+    let end: usize = (ip + 3).min(self.program.len());
+
+    // Decode only the instruction at `ip` (at most 3 bytes), so a bad byte
+    // further on in the program does not hide it.
+    let mut decoded: Result<Vec<(usize, Op)>, VmError> = Err(VmError::UnexpectedEndOfProgram);
+    for stop in ip + 1..=end {
+      decoded = decode_instructions(&self.program[ip..stop]);
+      if decoded.is_ok() {
+        break;
+      }
+    }
+
+    let rendered: String = match decoded {
+      Ok(instructions) => match instructions.into_iter().next() {
+        Some((_, op)) => self.render_traced_op(&op),
+        None => "<end of program>".to_string(),
+      },
+      Err(err) => format!("<undecodable: {err}>"),
+    };
+
+    println!("ip={ip:04} {rendered}  stack={:?}", self.stack);
+  }
+
+  fn render_traced_op(&self, op: &Op) -> String {
+    match op.jump_target() {
+      Some(target) => match self.symbols.get(&target) {
+        Some(name) => format!("{} {name}", op.mnemonic()),
+        None => op.to_source(),
+      },
+      None => op.to_source(),
+    }
+  }
+
+  /// Loads a fresh chunk of bytecode and runs it to completion, leaving
+  /// `stack` and `return_stack` intact across calls. This is what lets the
+  /// REPL assemble and execute one line at a time against persistent state.
+  fn run_line(&mut self, bytecode: Vec<u8>) -> Result<(), VmError> {
+    self.program = bytecode;
+    self.ip = 0;
+    self.run()
+  }
+
+  fn run_instruction(&mut self) -> Result<(), VmError> {
+    self.clock += 1;
+    if let Some(max_steps) = self.max_steps {
+      if self.clock > max_steps {
+        return Err(VmError::ExecutionLimitExceeded(max_steps));
+      }
+    }
+
     let opcode: u8 = self.program[self.ip];
     self.ip += 1;
 
@@ -90,90 +353,428 @@ impl VM {
       0x07 => self.dup(),
       0x08 => self.swap(),
       0x09 => self.drop(),
-      _ => panic!("Unknown opcode: {opcode}"),
+      0x0A => self.jmp(),
+      0x0B => self.jz(),
+      0x0C => self.jnz(),
+      0x0D => self.call(),
+      0x0E => self.ret(),
+      0x0F => self.div(),
+      0x10 => self.modulo(),
+      0x11 => self.and(),
+      0x12 => self.or(),
+      0x13 => self.xor(),
+      0x14 => self.not(),
+      0x15 => self.eq(),
+      0x16 => self.lt(),
+      0x17 => self.gt(),
+      _ => Err(VmError::UnknownOpcode(opcode)),
     }
   }
 
-  fn push(&mut self) {
-    let value: u8 = self.program[self.ip];
+  fn pop(&mut self, op: &'static str) -> Result<u8, VmError> {
+    self.stack.pop().ok_or(VmError::StackUnderflow { op })
+  }
+
+  fn byte(&mut self) -> Result<u8, VmError> {
+    let value: u8 = *self
+      .program
+      .get(self.ip)
+      .ok_or(VmError::UnexpectedEndOfProgram)?;
     self.ip += 1;
+    Ok(value)
+  }
+
+  fn push(&mut self) -> Result<(), VmError> {
+    let value: u8 = self.byte()?;
     self.stack.push(value);
+    Ok(())
   }
 
-  fn add(&mut self) {
-    let b: u8 = self.stack.pop().expect("Stack underflow");
-    let a: u8 = self.stack.pop().expect("Stack underflow");
-    let result: u8 = a.wrapping_add(b);
-    self.stack.push(result);
+  fn add(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("ADD")?;
+    let a: u8 = self.pop("ADD")?;
+    self.stack.push(a.wrapping_add(b));
+    Ok(())
   }
 
-  fn sub(&mut self) {
-    let b: u8 = self.stack.pop().expect("Stack underflow");
-    let a: u8 = self.stack.pop().expect("Stack underflow");
-    let result: u8 = a.wrapping_sub(b);
-    self.stack.push(result);
+  fn sub(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("SUB")?;
+    let a: u8 = self.pop("SUB")?;
+    self.stack.push(a.wrapping_sub(b));
+    Ok(())
   }
 
-  fn mul(&mut self) {
-    let b: u8 = self.stack.pop().expect("Stack underflow");
-    let a: u8 = self.stack.pop().expect("Stack underflow");
-    let result: u8 = a.wrapping_mul(b);
-    self.stack.push(result);
+  fn mul(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("MUL")?;
+    let a: u8 = self.pop("MUL")?;
+    self.stack.push(a.wrapping_mul(b));
+    Ok(())
   }
 
-  fn print(&mut self) {
-    let value: u8 = self.stack.pop().expect("Stack underflow");
+  fn print(&mut self) -> Result<(), VmError> {
+    let value: u8 = self.pop("PRINT")?;
     println!("{value}");
+    Ok(())
   }
 
-  fn print_top(&mut self) {
-    let value: u8 = *self.stack.last().expect("Stack underflow");
+  fn print_top(&mut self) -> Result<(), VmError> {
+    let value: u8 = *self.stack.last().ok_or(VmError::StackUnderflow { op: "PRINT_TOP" })?;
     println!("{value}");
+    Ok(())
   }
 
-  fn dup(&mut self) {
-    let value: u8 = *self.stack.last().expect("Stack underflow");
+  fn dup(&mut self) -> Result<(), VmError> {
+    let value: u8 = *self.stack.last().ok_or(VmError::StackUnderflow { op: "DUP" })?;
     self.stack.push(value);
+    Ok(())
   }
 
-  fn swap(&mut self) {
-    let b: u8 = self.stack.pop().expect("Stack underflow");
-    let a: u8 = self.stack.pop().expect("Stack underflow");
+  fn swap(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("SWAP")?;
+    let a: u8 = self.pop("SWAP")?;
     self.stack.push(b);
     self.stack.push(a);
+    Ok(())
+  }
+
+  fn drop(&mut self) -> Result<(), VmError> {
+    self.pop("DROP")?;
+    Ok(())
+  }
+
+  fn read_u16(&mut self) -> Result<usize, VmError> {
+    let lo: u8 = self.byte()?;
+    let hi: u8 = self.byte()?;
+    Ok(u16::from_le_bytes([lo, hi]) as usize)
+  }
+
+  fn jump_to(&mut self, target: usize) -> Result<(), VmError> {
+    if target > self.program.len() {
+      return Err(VmError::JumpOutOfBounds(target));
+    }
+
+    self.ip = target;
+    Ok(())
+  }
+
+  fn jmp(&mut self) -> Result<(), VmError> {
+    let target: usize = self.read_u16()?;
+    self.jump_to(target)
   }
 
-  fn drop(&mut self) {
-    self.stack.pop().expect("Stack underflow");
+  fn jz(&mut self) -> Result<(), VmError> {
+    let target: usize = self.read_u16()?;
+    let value: u8 = self.pop("JZ")?;
+    if value == 0 {
+      self.jump_to(target)?;
+    }
+    Ok(())
   }
+
+  fn jnz(&mut self) -> Result<(), VmError> {
+    let target: usize = self.read_u16()?;
+    let value: u8 = self.pop("JNZ")?;
+    if value != 0 {
+      self.jump_to(target)?;
+    }
+    Ok(())
+  }
+
+  fn call(&mut self) -> Result<(), VmError> {
+    let target: usize = self.read_u16()?;
+    self.return_stack.push(self.ip);
+    self.jump_to(target)
+  }
+
+  fn ret(&mut self) -> Result<(), VmError> {
+    match self.return_stack.pop() {
+      Some(address) => self.jump_to(address),
+      None => {
+        self.ip = self.program.len();
+        Ok(())
+      },
+    }
+  }
+
+  fn div(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("DIV")?;
+    let a: u8 = self.pop("DIV")?;
+    if b == 0 {
+      return Err(VmError::DivisionByZero);
+    }
+    self.stack.push(a / b);
+    Ok(())
+  }
+
+  fn modulo(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("MOD")?;
+    let a: u8 = self.pop("MOD")?;
+    if b == 0 {
+      return Err(VmError::DivisionByZero);
+    }
+    self.stack.push(a % b);
+    Ok(())
+  }
+
+  fn and(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("AND")?;
+    let a: u8 = self.pop("AND")?;
+    self.stack.push(a & b);
+    Ok(())
+  }
+
+  fn or(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("OR")?;
+    let a: u8 = self.pop("OR")?;
+    self.stack.push(a | b);
+    Ok(())
+  }
+
+  fn xor(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("XOR")?;
+    let a: u8 = self.pop("XOR")?;
+    self.stack.push(a ^ b);
+    Ok(())
+  }
+
+  fn not(&mut self) -> Result<(), VmError> {
+    let a: u8 = self.pop("NOT")?;
+    self.stack.push(!a);
+    Ok(())
+  }
+
+  fn eq(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("EQ")?;
+    let a: u8 = self.pop("EQ")?;
+    self.stack.push(u8::from(a == b));
+    Ok(())
+  }
+
+  fn lt(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("LT")?;
+    let a: u8 = self.pop("LT")?;
+    self.stack.push(u8::from(a < b));
+    Ok(())
+  }
+
+  fn gt(&mut self) -> Result<(), VmError> {
+    let b: u8 = self.pop("GT")?;
+    let a: u8 = self.pop("GT")?;
+    self.stack.push(u8::from(a > b));
+    Ok(())
+  }
+}
+
+fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+  assemble_with_symbols(source).map(|(bytecode, _)| bytecode)
 }
 
-fn assemble(source: &str) -> Vec<u8> {
+/// Same as `assemble`, but also returns the label table as a
+/// offset-to-name symbol map, for callers (like the tracer) that want to
+/// show label names instead of raw addresses.
+fn assemble_with_symbols(source: &str) -> Result<(Vec<u8>, HashMap<u16, String>), AssembleError> {
+  let lines: Vec<&str> = source.lines().collect();
+  let labels: HashMap<String, usize> = collect_labels(&lines);
+
   let mut bytecode: Vec<u8> = vec![];
-  for line in source.lines() {
-    if let ControlFlow::Break(_) = assemble_line(&mut bytecode, line) {
+  for line in &lines {
+    if let ControlFlow::Break(_) = assemble_line(&mut bytecode, line, &labels)? {
+      continue;
+    }
+  }
+
+  let symbols: HashMap<u16, String> = labels
+    .into_iter()
+    .map(|(name, offset)| (offset as u16, name))
+    .collect();
+
+  Ok((bytecode, symbols))
+}
+
+/// Pass one: walk the source computing the bytecode offset of every line
+/// without emitting any bytes, recording `label:` definitions as we go.
+fn collect_labels(lines: &[&str]) -> HashMap<String, usize> {
+  let mut labels: HashMap<String, usize> = HashMap::new();
+  let mut offset: usize = 0;
+
+  for line in lines {
+    let line: &str = line.trim();
+
+    if line.is_empty() {
       continue;
     }
+
+    if let Some(name) = line.strip_suffix(':') {
+      labels.insert(name.to_string(), offset);
+      continue;
+    }
+
+    let mnemonic: &str = line.split_whitespace().next().unwrap_or("");
+    offset += instruction_size(mnemonic);
   }
 
-  bytecode
+  labels
 }
 
-fn assemble_line(bytecode: &mut Vec<u8>, line: &str) -> ControlFlow<()> {
+/// Pass two: turn each line into ops, now that every label has a resolved
+/// offset. Label-definition lines are skipped; they emitted no bytes.
+fn assemble_line(
+  bytecode: &mut Vec<u8>,
+  line: &str,
+  labels: &HashMap<String, usize>,
+) -> Result<ControlFlow<()>, AssembleError> {
+  let line: &str = line.trim();
   let parts: Vec<&str> = line.split_whitespace().collect();
 
-  if parts.is_empty() {
-    return ControlFlow::Break(());
+  if parts.is_empty() || line.ends_with(':') {
+    return Ok(ControlFlow::Break(()));
   }
 
-  let op: Op = Op::from_parts(&parts);
+  let op: Op = Op::from_parts(&parts, labels)?;
   bytecode.extend(op.to_bytecode());
 
-  ControlFlow::Continue(())
+  Ok(ControlFlow::Continue(()))
 }
 
-fn main() {
-  let source: &'static str = r#"
+/// The inverse of `assemble`: walks bytecode decoding each opcode (and its
+/// operand, if any) back into mnemonics. Jump targets are rendered as
+/// synthetic `L<offset>` labels, with a matching `L<offset>:` definition
+/// emitted before whichever instruction sits at that offset, so the result
+/// round-trips through `assemble` into equivalent bytecode.
+fn disassemble(bytecode: &[u8]) -> Result<String, VmError> {
+  let instructions: Vec<(usize, Op)> = decode_instructions(bytecode)?;
+  let targets: HashSet<u16> = instructions
+    .iter()
+    .filter_map(|(_, op)| op.jump_target())
+    .collect();
+
+  let mut lines: Vec<String> = vec![];
+  for (offset, op) in &instructions {
+    if targets.contains(&(*offset as u16)) {
+      lines.push(format!("L{offset}:"));
+    }
+    lines.push(op.to_source());
+  }
+  if targets.contains(&(bytecode.len() as u16)) {
+    lines.push(format!("L{}:", bytecode.len()));
+  }
+
+  Ok(lines.join("\n"))
+}
+
+/// Bounds-checked; an unrecognized opcode or a truncated operand returns
+/// `VmError::UnknownOpcode`/`VmError::UnexpectedEndOfProgram` instead of
+/// panicking, the same as `run_instruction` would for the same bytes.
+fn decode_instructions(bytecode: &[u8]) -> Result<Vec<(usize, Op)>, VmError> {
+  let mut instructions: Vec<(usize, Op)> = vec![];
+  let mut ip: usize = 0;
+
+  while ip < bytecode.len() {
+    let offset: usize = ip;
+    let opcode: u8 = bytecode[ip];
+    ip += 1;
+
+    let op: Op = match opcode {
+      0x01 => {
+        let value: u8 = *bytecode.get(ip).ok_or(VmError::UnexpectedEndOfProgram)?;
+        ip += 1;
+        Op::Push(value)
+      },
+      0x02 => Op::Add,
+      0x03 => Op::Sub,
+      0x04 => Op::Mul,
+      0x05 => Op::Print,
+      0x06 => Op::PrintTop,
+      0x07 => Op::Dup,
+      0x08 => Op::Swap,
+      0x09 => Op::Drop,
+      0x0A => {
+        let target: u16 = read_u16_at(bytecode, ip)?;
+        ip += 2;
+        Op::Jmp(target)
+      },
+      0x0B => {
+        let target: u16 = read_u16_at(bytecode, ip)?;
+        ip += 2;
+        Op::Jz(target)
+      },
+      0x0C => {
+        let target: u16 = read_u16_at(bytecode, ip)?;
+        ip += 2;
+        Op::Jnz(target)
+      },
+      0x0D => {
+        let target: u16 = read_u16_at(bytecode, ip)?;
+        ip += 2;
+        Op::Call(target)
+      },
+      0x0E => Op::Ret,
+      0x0F => Op::Div,
+      0x10 => Op::Mod,
+      0x11 => Op::And,
+      0x12 => Op::Or,
+      0x13 => Op::Xor,
+      0x14 => Op::Not,
+      0x15 => Op::Eq,
+      0x16 => Op::Lt,
+      0x17 => Op::Gt,
+      _ => return Err(VmError::UnknownOpcode(opcode)),
+    };
+
+    instructions.push((offset, op));
+  }
+
+  Ok(instructions)
+}
+
+fn read_u16_at(bytecode: &[u8], ip: usize) -> Result<u16, VmError> {
+  let lo: u8 = *bytecode.get(ip).ok_or(VmError::UnexpectedEndOfProgram)?;
+  let hi: u8 = *bytecode.get(ip + 1).ok_or(VmError::UnexpectedEndOfProgram)?;
+  Ok(u16::from_le_bytes([lo, hi]))
+}
+
+/// Reads one line of assembly at a time from stdin and runs it against a
+/// long-lived `VM`, so the stack persists across inputs. Supports a few
+/// meta-commands: `.stack` reprints the current stack, `.reset` starts a
+/// fresh VM, and `.quit` exits.
+fn repl() {
+  use std::io::{self, BufRead, Write};
+
+  let mut vm: VM = VM::new(vec![]);
+  let stdin = io::stdin();
+
+  print!("llrs> ");
+  io::stdout().flush().ok();
+
+  for line in stdin.lock().lines() {
+    let Ok(line) = line else {
+      break;
+    };
+    let line: &str = line.trim();
+
+    match line {
+      ".quit" => break,
+      ".reset" => {
+        vm = VM::new(vec![]);
+        println!("Stack after execution: {:?}", vm.stack);
+      },
+      ".stack" => println!("Stack after execution: {:?}", vm.stack),
+      "" => {},
+      _ => match assemble(line) {
+        Ok(bytecode) => match vm.run_line(bytecode) {
+          Ok(()) => println!("Stack after execution: {:?}", vm.stack),
+          Err(err) => println!("Error: {err}"),
+        },
+        Err(err) => println!("Error: {err}"),
+      },
+    }
+
+    print!("llrs> ");
+    io::stdout().flush().ok();
+  }
+}
+
+const DEMO_SOURCE: &str = r#"
 PUSH 5
 PRINT_TOP
 PUSH 10
@@ -197,10 +798,45 @@ MUL
 PRINT
 "#;
 
-  let program: Vec<u8> = assemble(source);
-  let mut vm: VM = VM::new(program);
+/// Entry points: `repl` drops into the interactive REPL, `disassemble`
+/// prints the demo program's bytecode back out as source, `--trace` runs
+/// the demo program with step tracing on, and `--max-steps <n>` runs it
+/// under an execution budget. With no arguments it just runs the demo.
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+
+  match args.first().map(String::as_str) {
+    Some("repl") => {
+      repl();
+      return;
+    },
+    Some("disassemble") => {
+      let bytecode: Vec<u8> = assemble(DEMO_SOURCE).expect("Failed to assemble program");
+      let source: String = disassemble(&bytecode).expect("Failed to disassemble bytecode");
+      println!("{source}");
+      return;
+    },
+    _ => {},
+  }
+
+  let trace: bool = args.iter().any(|arg| arg == "--trace");
+  let max_steps: Option<u64> = args
+    .iter()
+    .position(|arg| arg == "--max-steps")
+    .and_then(|index| args.get(index + 1))
+    .and_then(|value| value.parse().ok());
+
+  let (program, symbols) =
+    assemble_with_symbols(DEMO_SOURCE).expect("Failed to assemble program");
+
+  let mut vm: VM = match max_steps {
+    Some(max_steps) => VM::with_limit(program, max_steps),
+    None => VM::new(program),
+  };
+  vm.set_symbols(symbols);
+  vm.trace = trace;
 
-  vm.run();
+  vm.run().expect("VM execution failed");
 
   println!("Stack after execution: {:?}", vm.stack)
 }